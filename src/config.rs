@@ -1,6 +1,8 @@
 //! Toml config module
 
 use crate::error::{Error, Result};
+use crate::secret::SecretKey;
+use crate::wire::Format;
 use serde_derive::{Deserialize, Serialize};
 use std::env::current_dir;
 use std::fs::File;
@@ -12,17 +14,63 @@ use std::path::{Path, PathBuf};
 pub struct Config {
     pub host: String,
     pub port: usize,
+    /// The wire format used to (de)serialize messages.
+    #[serde(default)]
+    pub format: Format,
+    /// When set, payloads are sealed with XChaCha20-Poly1305 using this
+    /// pre-shared key. Both peers must configure the same key.
+    #[serde(default)]
+    pub secret_key: Option<SecretKey>,
+    /// The largest message, in bytes, a `Source`/`Sink` will send or
+    /// receive. Guards against a misbehaving peer exhausting memory.
+    #[serde(default = "default_max_content_length")]
+    pub max_content_length: usize,
+    /// The ZMQ socket topology to use.
+    #[serde(default)]
+    pub pattern: Pattern,
+    /// In [`Pattern::PubSub`] mode, the topic prefix a `Source` publishes
+    /// under and a `Sink` subscribes to. `None` subscribes to everything.
+    #[serde(default)]
+    pub topic: Option<String>,
 }
 
+/// A few megabytes: generous for typical IPC payloads, small enough to
+/// bound a single allocation.
+fn default_max_content_length() -> usize { 8 * 1024 * 1024 }
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             host: "127.0.0.1".to_string(),
             port: 10001,
+            format: Format::default(),
+            secret_key: None,
+            max_content_length: default_max_content_length(),
+            pattern: Pattern::default(),
+            topic: None,
         }
     }
 }
 
+/// The ZMQ socket topology a `Source`/`Sink` pair is built around.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Deserialize, Serialize)]
+pub enum Pattern {
+    /// One `Source` talks to one `Sink` at a time; every message gets a
+    /// synchronous ACK reply. The default, and the only mode with a
+    /// version/capability handshake.
+    ReqRep,
+    /// A `Source` broadcasts to any number of `Sink`s; fire-and-forget.
+    PubSub,
+    /// Any number of `Source`s feed a load-balanced pipeline of `Sink`s;
+    /// fire-and-forget.
+    PushPull,
+}
+
+impl Default for Pattern {
+    fn default() -> Self { Pattern::ReqRep }
+}
+
 impl Config {
     /// Parse a TOML config file.
     /// If the file can't be found, default settings are assumed and returned.
@@ -67,6 +115,69 @@ impl Config {
         Ok(())
     }
 
+    /// Load a `Config`, picking the right config file(s):
+    /// - If `custom` is given, only that file is read (like `parse_toml`).
+    /// - Otherwise, the system-global config (see [`Self::global_config_path`])
+    ///   and the per-user config (see [`Self::user_config_path`]) are both
+    ///   read, if present, and merged field-by-field with the user config
+    ///   taking precedence. Missing files are skipped, not errors.
+    pub fn load_multi(custom: Option<PathBuf>) -> Result<Self> {
+        if let Some(custom_path) = custom {
+            return Self::parse_toml(custom_path);
+        }
+        let global = Self::read_toml_value(&Self::global_config_path())?;
+        let user = match Self::user_config_path() {
+            Some(path) => Self::read_toml_value(&path)?,
+            None => None,
+        };
+        let merged = match (global, user) {
+            (Some(global), Some(user)) => Self::merge_toml(global, user),
+            (Some(global), None) => global,
+            (None, Some(user)) => user,
+            (None, None) => return Ok(Self::default()),
+        };
+        Ok(merged.try_into()?)
+    }
+
+    /// The system-wide config file, shared by every user on this machine.
+    pub fn global_config_path() -> PathBuf {
+        PathBuf::from("/etc/ipc-chan/config.toml")
+    }
+
+    /// The per-user config file, which takes precedence over
+    /// [`Self::global_config_path`] in [`Self::load_multi`].
+    pub fn user_config_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("ipc-chan").join("config.toml"))
+    }
+
+    /// Read `path` as a TOML value, or `None` if it doesn't exist.
+    fn read_toml_value(path: &Path) -> Result<Option<toml::Value>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let mut contents = String::new();
+        File::open(path)?.read_to_string(&mut contents)?;
+        Ok(Some(toml::from_str(&contents)?))
+    }
+
+    /// Overlay `overlay` onto `base`, recursing into nested tables and
+    /// letting `overlay`'s values win on conflicts.
+    fn merge_toml(base: toml::Value, overlay: toml::Value) -> toml::Value {
+        match (base, overlay) {
+            (toml::Value::Table(mut base), toml::Value::Table(overlay)) => {
+                for (key, value) in overlay {
+                    let merged = match base.remove(&key) {
+                        Some(base_value) => Self::merge_toml(base_value, value),
+                        None => value,
+                    };
+                    base.insert(key, merged);
+                }
+                toml::Value::Table(base)
+            },
+            (_, overlay) => overlay,
+        }
+    }
+
     /// Search for a file in ancestor directories, then in $HOME.
     /// First in the parent dir, then in the parent's parent dir, etc.
     /// Return `None` if the file could not be found anywhere.
@@ -100,6 +211,13 @@ impl Config {
             _ => None,
         }
     }
+
+    /// Apply `overrides` on top of `self`, field by field, leaving fields
+    /// `self` alone wherever `overrides` doesn't specify a value.
+    pub fn apply_overrides(&mut self, overrides: ConfigOverride) {
+        if let Some(host) = overrides.host { self.host = host; }
+        if let Some(port) = overrides.port { self.port = port; }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -108,3 +226,24 @@ pub enum OverwritePolicy {
     DontOverwrite,
     Overwrite,
 }
+
+/// Overrides for [`Config`] fields, typically sourced from CLI flags or
+/// environment variables, applied after a config file has been loaded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConfigOverride {
+    pub host: Option<String>,
+    pub port: Option<usize>,
+}
+
+impl ConfigOverride {
+    /// Build a `ConfigOverride` from the `IPC_CHAN_HOST`/`IPC_CHAN_PORT`
+    /// environment variables. Fields are left unset if the corresponding
+    /// variable is absent or can't be parsed.
+    pub fn from_env() -> Self {
+        Self {
+            host: std::env::var("IPC_CHAN_HOST").ok(),
+            port: std::env::var("IPC_CHAN_PORT").ok()
+                .and_then(|port| port.parse().ok()),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! AEAD encryption of wire payloads using a pre-shared [`SecretKey`].
+
+use crate::error::{Error, Result};
+use crate::secret::SecretKey;
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use rand::RngCore;
+
+/// XChaCha20-Poly1305 uses a 24-byte extended nonce.
+const NONCE_LEN: usize = 24;
+
+/// Seal `plaintext` with `key`, returning `nonce || ciphertext || tag`.
+/// A fresh random nonce is generated for every call.
+pub fn seal(key: &SecretKey, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_bytes()));
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = XNonce::from_slice(&nonce_bytes);
+    let ciphertext = cipher.encrypt(nonce, plaintext)
+        .expect("XChaCha20-Poly1305 encryption failed");
+    let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(&nonce_bytes);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Open bytes produced by [`seal`], failing with [`Error::DecryptionError`]
+/// if the AEAD tag doesn't verify.
+pub fn open(key: &SecretKey, sealed: &[u8]) -> Result<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(Error::DecryptionError);
+    }
+    let (nonce_bytes, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_bytes()));
+    let nonce = XNonce::from_slice(nonce_bytes);
+    cipher.decrypt(nonce, ciphertext).map_err(|_| Error::DecryptionError)
+}
@@ -1,5 +1,6 @@
 //!
 
+use crate::handshake::Handshake;
 use serde_json::{Error as JsonError};
 use zmq::{Error as ZmqError};
 
@@ -12,8 +13,26 @@ pub enum Error {
     IoError(std::io::Error),
     /// JSON de/serialization failure
     JsonError(JsonError),
-    /// Expected the bytes to be `UTF-8`, but they're not
-    NotUtf8Error(Vec<u8>),
+    /// The peer's major protocol version doesn't match ours
+    VersionMismatch { local: Handshake, remote: Handshake },
+    /// One peer has encryption configured and the other doesn't
+    EncryptionMismatch { local_enabled: bool, remote_enabled: bool },
+    /// The configured `SecretKey` string isn't valid hex or base64, or
+    /// doesn't decode to the right number of bytes
+    InvalidSecretKey,
+    /// AEAD decryption failed; the tag didn't verify, or the keys differ
+    DecryptionError,
+    /// A message exceeded the `max_content_length` in effect (negotiated
+    /// with the peer in `Pattern::ReqRep`, the configured value otherwise)
+    MessageTooLarge { size: usize, limit: usize },
+    /// MessagePack serialization failure
+    MsgPackEncodeError(rmp_serde::encode::Error),
+    /// MessagePack deserialization failure
+    MsgPackDecodeError(rmp_serde::decode::Error),
+    /// CBOR serialization failure
+    CborEncodeError(ciborium::ser::Error<std::io::Error>),
+    /// CBOR deserialization failure
+    CborDecodeError(ciborium::de::Error<std::io::Error>),
     /// Failed to deserialize from TOML file
     TomlDeserializeError(toml::de::Error),
     /// Failed to serialize to TOML file
@@ -30,6 +49,22 @@ impl From<JsonError> for Error {
     fn from(e: JsonError) -> Self { Self::JsonError(e) }
 }
 
+impl From<rmp_serde::encode::Error> for Error {
+    fn from(e: rmp_serde::encode::Error) -> Self { Self::MsgPackEncodeError(e) }
+}
+
+impl From<rmp_serde::decode::Error> for Error {
+    fn from(e: rmp_serde::decode::Error) -> Self { Self::MsgPackDecodeError(e) }
+}
+
+impl From<ciborium::ser::Error<std::io::Error>> for Error {
+    fn from(e: ciborium::ser::Error<std::io::Error>) -> Self { Self::CborEncodeError(e) }
+}
+
+impl From<ciborium::de::Error<std::io::Error>> for Error {
+    fn from(e: ciborium::de::Error<std::io::Error>) -> Self { Self::CborDecodeError(e) }
+}
+
 impl From<toml::de::Error> for Error {
     fn from(e: toml::de::Error) -> Self { Self::TomlDeserializeError(e) }
 }
@@ -0,0 +1,54 @@
+//! Connection handshake: the first message(s) exchanged between a
+//! `Source` and a `Sink`, used to detect version mismatches and
+//! advertise optional capabilities before any application data flows.
+
+use crate::config::Config;
+use crate::error::{Error, Result};
+use serde_derive::{Deserialize, Serialize};
+
+/// The wire protocol version implemented by this build of the crate.
+/// Bump the major component on breaking wire-protocol changes.
+pub const PROTOCOL_VERSION: (u16, u16, u16) = (1, 0, 0);
+
+/// A peer's protocol version and feature set, exchanged once per
+/// connection. The negotiated peer `Handshake` is available via
+/// [`Source::peer`](crate::Source::peer)/[`Sink::peer`](crate::Sink::peer).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Serialize)]
+pub struct Handshake {
+    /// `(major, minor, patch)` wire protocol version.
+    pub protocol: (u16, u16, u16),
+    /// The `ipc-chan` crate version the peer was built against.
+    pub crate_version: String,
+    /// Optional features the peer supports, e.g. `"encryption"`.
+    pub capabilities: Vec<String>,
+    /// The largest message, in bytes, this peer is willing to send or
+    /// receive. See [`Config::max_content_length`].
+    pub max_content_length: usize,
+}
+
+impl Handshake {
+    /// Build the `Handshake` describing this build of the crate as
+    /// configured by `cfg`, advertising `capabilities` as given by the
+    /// caller.
+    pub fn local(cfg: &Config, capabilities: Vec<String>) -> Self {
+        Self {
+            protocol: PROTOCOL_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            capabilities,
+            max_content_length: cfg.max_content_length,
+        }
+    }
+
+    /// Fail with [`Error::VersionMismatch`] if `self` (the local peer)
+    /// and `remote` disagree on the major protocol version.
+    pub fn check_compatible(&self, remote: &Handshake) -> Result<()> {
+        if self.protocol.0 != remote.protocol.0 {
+            return Err(Error::VersionMismatch {
+                local: self.clone(),
+                remote: remote.clone(),
+            });
+        }
+        Ok(())
+    }
+}
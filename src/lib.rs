@@ -2,12 +2,43 @@
 
 mod error;
 mod config;
+mod wire;
+mod handshake;
+mod secret;
+mod encryption;
 
 pub use crate::error::{Error, Result};
-pub use crate::config::Config;
+pub use crate::config::{Config, ConfigOverride, Pattern};
+pub use crate::wire::{Format, WireFormat};
+pub use crate::handshake::{Handshake, PROTOCOL_VERSION};
+pub use crate::secret::SecretKey;
 use std::path::Path;
 use serde::{Deserialize, Serialize};
 
+/// The capability name a peer advertises in its [`Handshake`] when it has
+/// a `secret_key` configured.
+const ENCRYPTION_CAPABILITY: &str = "encryption";
+
+/// The capabilities `cfg` allows us to advertise in our `Handshake`.
+fn local_capabilities(cfg: &Config) -> Vec<String> {
+    let mut capabilities = Vec::new();
+    if cfg.secret_key.is_some() {
+        capabilities.push(ENCRYPTION_CAPABILITY.to_string());
+    }
+    capabilities
+}
+
+/// Fail with `Error::EncryptionMismatch` unless `cfg` and `peer` agree on
+/// whether payloads are encrypted.
+fn check_encryption_agreement(cfg: &Config, peer: &Handshake) -> Result<()> {
+    let local_enabled = cfg.secret_key.is_some();
+    let remote_enabled = peer.capabilities.iter().any(|c| c == ENCRYPTION_CAPABILITY);
+    if local_enabled != remote_enabled {
+        return Err(Error::EncryptionMismatch { local_enabled, remote_enabled });
+    }
+    Ok(())
+}
+
 
 /// It's a little passive-aggressive, but it'll work.
 const ACK: &str = "K";
@@ -28,6 +59,14 @@ pub struct Source {
     socket: zmq::Socket,
     #[allow(unused)]
     cfg: Config,
+    /// The negotiated `Handshake` of the connected `Sink`.
+    /// Only populated in [`Pattern::ReqRep`], which is the only mode with
+    /// a handshake; `None` otherwise.
+    peer: Option<Handshake>,
+    /// `min(cfg.max_content_length, peer.max_content_length)` in
+    /// `Pattern::ReqRep`, so we fail fast locally instead of sending
+    /// something the peer will reject; otherwise just `cfg.max_content_length`.
+    limit: usize,
 }
 
 impl Source {
@@ -38,24 +77,61 @@ impl Source {
 
     pub fn from_config(cfg: Config) -> Result<Self> {
         let ctx = zmq::Context::new();
-        let socket = ctx.socket(zmq::REQ)?;
+        let socket_type = match cfg.pattern {
+            Pattern::ReqRep => zmq::REQ,
+            Pattern::PubSub => zmq::PUB,
+            Pattern::PushPull => zmq::PUSH,
+        };
+        let mut socket = ctx.socket(socket_type)?;
+        // Reject oversized inbound frames at the socket, before they're
+        // ever allocated into a `Vec`, instead of only after `recv_bytes`
+        // has already paid for the allocation.
+        socket.set_maxmsgsize(cfg.max_content_length as i64)?;
         socket.connect(&format!("tcp://{}:{}", cfg.host, cfg.port))?;
-        Ok(Self { ctx, socket, cfg })
+        let (peer, limit) = match cfg.pattern {
+            Pattern::ReqRep => {
+                let local = Handshake::local(&cfg, local_capabilities(&cfg));
+                imp::send_handshake(&mut socket, &local, &cfg)?;
+                let peer = imp::recv_handshake(&mut socket, &cfg)?;
+                local.check_compatible(&peer)?;
+                check_encryption_agreement(&cfg, &peer)?;
+                let limit = cfg.max_content_length.min(peer.max_content_length);
+                socket.set_maxmsgsize(limit as i64)?;
+                (Some(peer), limit)
+            },
+            Pattern::PubSub | Pattern::PushPull => (None, cfg.max_content_length),
+        };
+        Ok(Self { ctx, socket, cfg, peer, limit })
     }
 
     /// Send a value of type `V`.
     /// Return `Ok(())` if the value was sent successfully;
     /// Otherwise return an error.
+    ///
+    /// In [`Pattern::ReqRep`] this waits for the `Sink`'s ACK before
+    /// returning; in [`Pattern::PubSub`]/[`Pattern::PushPull`] it's
+    /// fire-and-forget.
     pub fn send<V>(&mut self, value: &V) -> Result<()>
     where V: ?Sized + Serialize {
-        imp::send(&mut self.socket, value)?;
-        let reply: String = imp::recv(&mut self.socket)?;
-        debug_assert_eq!(reply, ACK);
-        Ok(())
+        match self.cfg.pattern {
+            Pattern::ReqRep => {
+                imp::send(&mut self.socket, value, &self.cfg, self.limit)?;
+                let reply: String = imp::recv(&mut self.socket, &self.cfg, self.limit)?;
+                debug_assert_eq!(reply, ACK);
+                Ok(())
+            },
+            Pattern::PubSub => imp::send_pub(&mut self.socket, value, &self.cfg, self.limit),
+            Pattern::PushPull => imp::send(&mut self.socket, value, &self.cfg, self.limit),
+        }
     }
 
     #[inline(always)]
     pub fn config(&self) -> &Config { &self.cfg }
+
+    /// The negotiated `Handshake` of the connected `Sink`, or `None`
+    /// outside [`Pattern::ReqRep`] (no handshake is performed there).
+    #[inline(always)]
+    pub fn peer(&self) -> Option<&Handshake> { self.peer.as_ref() }
 }
 
 
@@ -65,6 +141,15 @@ pub struct Sink {
     socket: zmq::Socket,
     #[allow(unused)]
     cfg: Config,
+    /// The negotiated `Handshake` of the most recently connected `Source`.
+    /// Only populated in [`Pattern::ReqRep`], which is the only mode with
+    /// a handshake; `None` otherwise.
+    peer: Option<Handshake>,
+    /// `min(cfg.max_content_length, peer.max_content_length)` for the most
+    /// recently negotiated `Pattern::ReqRep` peer, so both ends agree on
+    /// the ceiling; just `cfg.max_content_length` before any peer has
+    /// negotiated, or outside `Pattern::ReqRep`.
+    limit: usize,
 }
 
 impl Sink {
@@ -75,21 +160,71 @@ impl Sink {
 
     pub fn from_config(cfg: Config) -> Result<Self> {
         let ctx = zmq::Context::new();
-        let socket = ctx.socket(zmq::REP)?;
+        let socket_type = match cfg.pattern {
+            Pattern::ReqRep => zmq::REP,
+            Pattern::PubSub => zmq::SUB,
+            Pattern::PushPull => zmq::PULL,
+        };
+        let mut socket = ctx.socket(socket_type)?;
+        // Reject oversized inbound frames at the socket, before they're
+        // ever allocated into a `Vec`, instead of only after `recv_bytes`
+        // has already paid for the allocation.
+        socket.set_maxmsgsize(cfg.max_content_length as i64)?;
         socket.bind(&format!("tcp://*:{}", cfg.port))?;
-        Ok(Self { ctx, socket, cfg })
+        if cfg.pattern == Pattern::PubSub {
+            let topic = cfg.topic.clone().unwrap_or_default();
+            socket.set_subscribe(topic.as_bytes())?;
+        }
+        // No handshake here, even for `Pattern::ReqRep`: binding must stay
+        // non-blocking. The first `recv()` negotiates it lazily with
+        // whichever `Source` connects first; see the `Frame::Handshake`
+        // arm there.
+        let limit = cfg.max_content_length;
+        Ok(Self { ctx, socket, cfg, peer: None, limit })
     }
 
 
+    /// Receive a value of type `V`.
+    ///
+    /// In [`Pattern::ReqRep`] this transparently handles the `Handshake`
+    /// any newly-connected `Source` sends before its first real message,
+    /// replying in kind, and ACKs every real message. In
+    /// [`Pattern::PubSub`]/[`Pattern::PushPull`] there's no handshake or
+    /// ACK; this simply blocks for the next message.
     pub fn recv<V>(&mut self) -> Result<V>
     where V: for<'de> Deserialize<'de> {
-        let msg: V = imp::recv(&mut self.socket)?;
-        imp::send(&mut self.socket, ACK)?;
-        Ok(msg)
+        match self.cfg.pattern {
+            Pattern::ReqRep => loop {
+                match imp::recv_frame(&mut self.socket, &self.cfg, self.limit)? {
+                    imp::Frame::Handshake(peer) => {
+                        let local = Handshake::local(&self.cfg, local_capabilities(&self.cfg));
+                        local.check_compatible(&peer)?;
+                        check_encryption_agreement(&self.cfg, &peer)?;
+                        imp::send_handshake(&mut self.socket, &local, &self.cfg)?;
+                        self.limit = self.cfg.max_content_length.min(peer.max_content_length);
+                        self.socket.set_maxmsgsize(self.limit as i64)?;
+                        self.peer = Some(peer);
+                    },
+                    imp::Frame::Data(bytes) => {
+                        let msg: V = imp::decode_data(&self.cfg, &bytes)?;
+                        imp::send(&mut self.socket, ACK, &self.cfg, self.limit)?;
+                        return Ok(msg);
+                    },
+                }
+            },
+            Pattern::PubSub => imp::recv_sub(&mut self.socket, &self.cfg, self.limit),
+            Pattern::PushPull => imp::recv(&mut self.socket, &self.cfg, self.limit),
+        }
     }
 
     #[inline(always)]
     pub fn config(&self) -> &Config { &self.cfg }
+
+    /// The negotiated `Handshake` of the most recently connected `Source`,
+    /// or `None` outside [`Pattern::ReqRep`] (no handshake is performed
+    /// there).
+    #[inline(always)]
+    pub fn peer(&self) -> Option<&Handshake> { self.peer.as_ref() }
 }
 
 
@@ -98,21 +233,157 @@ mod imp {
 
     const NO_FLAGS: i32 = 0;
 
+    /// What a received wire message turned out to be: a version/capability
+    /// [`Handshake`], or application `Data`.
+    pub(super) enum Frame {
+        Handshake(Handshake),
+        Data(Vec<u8>),
+    }
+
+    /// 1-byte tag prepended to every wire message so a `Sink` can tell a
+    /// `Handshake` apart from `Data` without running `V`'s codec, and
+    /// without re-encoding the `Data` payload's bytes through `cfg.format`
+    /// a second time (that would, say, turn a `Vec<u8>` into a JSON array
+    /// of integers on the default format).
+    const TAG_HANDSHAKE: u8 = 0;
+    const TAG_DATA: u8 = 1;
+
+    /// Prepend `tag` to `body` and write it to `socket` in one message,
+    /// unconditionally in plaintext. Encryption, where applicable, is
+    /// applied to a `Data` payload *before* it reaches here (see [`send`])
+    /// -- a `Handshake` is how peers negotiate whether encryption is even
+    /// on, so it can never itself be sealed, or `check_encryption_agreement`
+    /// could never run.
+    #[inline(always)]
+    fn send_tagged(socket: &mut zmq::Socket, tag: u8, body: &[u8], limit: usize) -> Result<()> {
+        let mut bytes = Vec::with_capacity(1 + body.len());
+        bytes.push(tag);
+        bytes.extend_from_slice(body);
+        if bytes.len() > limit {
+            return Err(Error::MessageTooLarge { size: bytes.len(), limit });
+        }
+        socket.send(&bytes, NO_FLAGS)?;
+        Ok(())
+    }
+
     #[inline(always)]
-    pub(super) fn send<V>(socket: &mut zmq::Socket, value: &V) -> Result<()>
+    pub(super) fn recv_frame(
+        socket: &mut zmq::Socket, cfg: &Config, limit: usize,
+    ) -> Result<Frame> {
+        let bytes: Vec<u8> = socket.recv_bytes(NO_FLAGS)?;
+        if bytes.len() > limit {
+            return Err(Error::MessageTooLarge { size: bytes.len(), limit });
+        }
+        let (&tag, body) = bytes.split_first().ok_or_else(|| Error::IoError(
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "received an empty frame"),
+        ))?;
+        match tag {
+            TAG_HANDSHAKE => Ok(Frame::Handshake(cfg.format.decode(body)?)),
+            TAG_DATA => Ok(Frame::Data(body.to_vec())),
+            tag => Err(Error::IoError(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("received an unknown frame tag: {tag}"),
+            ))),
+        }
+    }
+
+    #[inline(always)]
+    pub(super) fn send<V>(
+        socket: &mut zmq::Socket, value: &V, cfg: &Config, limit: usize,
+    ) -> Result<()>
+    where V: ?Sized + Serialize {
+        let bytes: Vec<u8> = cfg.format.encode(value)?;
+        let bytes: Vec<u8> = match &cfg.secret_key {
+            Some(key) => encryption::seal(key, &bytes)?,
+            None => bytes,
+        };
+        send_tagged(socket, TAG_DATA, &bytes, limit)
+    }
+
+    #[inline(always)]
+    pub(super) fn recv<V>(socket: &mut zmq::Socket, cfg: &Config, limit: usize) -> Result<V>
+    where V: for<'de> Deserialize<'de> {
+        match recv_frame(socket, cfg, limit)? {
+            Frame::Data(bytes) => decode_data(cfg, &bytes),
+            Frame::Handshake(_) => Err(Error::IoError(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "expected data, got a Handshake frame",
+            ))),
+        }
+    }
+
+    /// Decrypt (if configured) and decode a `Frame::Data` payload.
+    #[inline(always)]
+    pub(super) fn decode_data<V>(cfg: &Config, bytes: &[u8]) -> Result<V>
+    where V: for<'de> Deserialize<'de> {
+        let bytes: Vec<u8> = match &cfg.secret_key {
+            Some(key) => encryption::open(key, bytes)?,
+            None => bytes.to_vec(),
+        };
+        cfg.format.decode(&bytes)
+    }
+
+    #[inline(always)]
+    pub(super) fn send_handshake(
+        socket: &mut zmq::Socket, handshake: &Handshake, cfg: &Config,
+    ) -> Result<()> {
+        let body: Vec<u8> = cfg.format.encode(handshake)?;
+        send_tagged(socket, TAG_HANDSHAKE, &body, cfg.max_content_length)
+    }
+
+    #[inline(always)]
+    pub(super) fn recv_handshake(socket: &mut zmq::Socket, cfg: &Config) -> Result<Handshake> {
+        match recv_frame(socket, cfg, cfg.max_content_length)? {
+            Frame::Handshake(handshake) => Ok(handshake),
+            Frame::Data(_) => Err(Error::IoError(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "expected a Handshake frame, got data",
+            ))),
+        }
+    }
+
+    /// Publish `value` with `cfg.topic` prepended as a raw byte prefix, so
+    /// a subscribing `Sink`'s `set_subscribe` filter can match on it.
+    /// Unlike [`send`], this is never wrapped in a [`Frame`]: the topic
+    /// must stay an unencrypted literal prefix for ZMQ's SUB-side
+    /// filtering to see it.
+    #[inline(always)]
+    pub(super) fn send_pub<V>(
+        socket: &mut zmq::Socket, value: &V, cfg: &Config, limit: usize,
+    ) -> Result<()>
     where V: ?Sized + Serialize {
-        let s: String = serde_json::to_string(value)?;
-        socket.send(&s, NO_FLAGS)?;
+        let payload: Vec<u8> = cfg.format.encode(value)?;
+        let payload: Vec<u8> = match &cfg.secret_key {
+            Some(key) => encryption::seal(key, &payload)?,
+            None => payload,
+        };
+        let topic = cfg.topic.as_deref().unwrap_or("");
+        let mut bytes = Vec::with_capacity(topic.len() + payload.len());
+        bytes.extend_from_slice(topic.as_bytes());
+        bytes.extend_from_slice(&payload);
+        if bytes.len() > limit {
+            return Err(Error::MessageTooLarge { size: bytes.len(), limit });
+        }
+        socket.send(&bytes, NO_FLAGS)?;
         Ok(())
     }
 
+    /// Receive a value published via [`send_pub`], stripping `cfg.topic`'s
+    /// raw byte prefix before decoding.
     #[inline(always)]
-    pub(super) fn recv<V>(socket: &mut zmq::Socket) -> Result<V>
+    pub(super) fn recv_sub<V>(socket: &mut zmq::Socket, cfg: &Config, limit: usize) -> Result<V>
     where V: for<'de> Deserialize<'de> {
-        match socket.recv_string(NO_FLAGS)? {
-            Ok(s) => Ok(serde_json::from_str::<V>(&s)?),
-            Err(bytes) => Err(Error::NotUtf8Error(bytes)),
+        let bytes: Vec<u8> = socket.recv_bytes(NO_FLAGS)?;
+        if bytes.len() > limit {
+            return Err(Error::MessageTooLarge { size: bytes.len(), limit });
         }
+        let topic_len = cfg.topic.as_deref().unwrap_or("").len();
+        let payload = &bytes[topic_len..];
+        let payload: Vec<u8> = match &cfg.secret_key {
+            Some(key) => encryption::open(key, payload)?,
+            None => payload.to_vec(),
+        };
+        cfg.format.decode(&payload)
     }
 }
 
@@ -132,10 +403,15 @@ mod tests {
         let cfg = Config {
             host: "127.0.0.1".to_string(),
             port: 11001, // test-specific port
+            format: Format::default(),
+            secret_key: None,
+            max_content_length: Config::default().max_content_length,
+            pattern: Pattern::default(),
+            topic: None,
         };
-        let mut source = Source::from_config(cfg.clone())?;
-        let mut   sink =   Sink::from_config(cfg.clone())?;
+        let sink_cfg = cfg.clone();
         let thread_guard = std::thread::spawn(move || {
+            let mut sink = Sink::from_config(sink_cfg).expect("Sink failed to bind");
             let msg0: String = sink.recv().expect("Sink failed to receive MSG0");
             assert_eq!(msg0, "Hello World! 0");
             let msg1: String = sink.recv().expect("Sink failed to receive MSG1");
@@ -143,6 +419,7 @@ mod tests {
             let msg2: Foo = sink.recv().expect("Sink failed to receive MSG2");
             assert_eq!(msg2, Foo("Hello World! 2".to_string(), 42));
         });
+        let mut source = Source::from_config(cfg)?;
         source.send("Hello World! 0")?;
         source.send("Hello World! 1")?;
         source.send(&Foo("Hello World! 2".to_string(), 42))?;
@@ -155,11 +432,15 @@ mod tests {
         let cfg = Config {
             host: "127.0.0.1".to_string(),
             port: 11002, // test-specific port
+            format: Format::default(),
+            secret_key: None,
+            max_content_length: Config::default().max_content_length,
+            pattern: Pattern::default(),
+            topic: None,
         };
-        let mut source0 = Source::from_config(cfg.clone())?;
-        let mut source1 = Source::from_config(cfg.clone())?;
-        let mut    sink =   Sink::from_config(cfg.clone())?;
+        let sink_cfg = cfg.clone();
         let thread_guard = std::thread::spawn(move || {
+            let mut sink = Sink::from_config(sink_cfg).expect("Sink failed to bind");
             let msg0: String = sink.recv().expect("Sink failed to receive MSG0");
             assert_eq!(msg0, "Hello World! 0");
             let msg1: String = sink.recv().expect("Sink failed to receive MSG1");
@@ -167,6 +448,8 @@ mod tests {
             let msg2: Foo = sink.recv().expect("Sink failed to receive MSG2");
             assert_eq!(msg2, Foo("Hello World! 2".to_string(), 42));
         });
+        let mut source0 = Source::from_config(cfg.clone())?;
+        let mut source1 = Source::from_config(cfg)?;
         source0.send("Hello World! 0")?;
         source1.send("Hello World! 1")?;
         source0.send(&Foo("Hello World! 2".to_string(), 42))?;
@@ -179,11 +462,15 @@ mod tests {
         let cfg = Config {
             host: "127.0.0.1".to_string(),
             port: 11003, // test-specific port
+            format: Format::default(),
+            secret_key: None,
+            max_content_length: Config::default().max_content_length,
+            pattern: Pattern::default(),
+            topic: None,
         };
-        let mut source0 = Source::from_config(cfg.clone())?;
-        let mut source1 = Source::from_config(cfg.clone())?;
-        let mut    sink =   Sink::from_config(cfg.clone())?;
+        let sink_cfg = cfg.clone();
         let thread_guard = std::thread::spawn(move || {
+            let mut sink = Sink::from_config(sink_cfg).expect("Sink failed to bind");
             let msg0: String = sink.recv().expect("Sink failed to receive msg0");
             assert_eq!(msg0, "Hello World! 0");
             let msg1: String = sink.recv().expect("Sink failed to receive msg1");
@@ -191,6 +478,8 @@ mod tests {
             let msg2: Foo = sink.recv().expect("Sink failed to receive msg2");
             assert_eq!(msg2, Foo("Hello World! 2".to_string(), 42));
         });
+        let mut source0 = Source::from_config(cfg.clone())?;
+        let mut source1 = Source::from_config(cfg)?;
         sendstr!(source0, "Hello World! {}", 0)?;
         sendstr!(source1, "Hello World! {}", 1)?;
         source0.send(&Foo("Hello World! 2".to_string(), 42))?;
@@ -207,4 +496,147 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn push_pull_round_trip() -> Result<()> {
+        let cfg = Config {
+            host: "127.0.0.1".to_string(),
+            port: 11005, // test-specific port
+            format: Format::default(),
+            secret_key: None,
+            max_content_length: Config::default().max_content_length,
+            pattern: Pattern::PushPull,
+            topic: None,
+        };
+        let sink_cfg = cfg.clone();
+        let thread_guard = std::thread::spawn(move || {
+            let mut sink = Sink::from_config(sink_cfg).expect("Sink failed to bind");
+            let msg0: String = sink.recv().expect("Sink failed to receive MSG0");
+            assert_eq!(msg0, "Hello World! 0");
+            let msg1: Foo = sink.recv().expect("Sink failed to receive MSG1");
+            assert_eq!(msg1, Foo("Hello World! 1".to_string(), 42));
+        });
+        let mut source = Source::from_config(cfg)?;
+        source.send("Hello World! 0")?;
+        source.send(&Foo("Hello World! 1".to_string(), 42))?;
+        thread_guard.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn pub_sub_round_trip() -> Result<()> {
+        let cfg = Config {
+            host: "127.0.0.1".to_string(),
+            port: 11006, // test-specific port
+            format: Format::default(),
+            secret_key: None,
+            max_content_length: Config::default().max_content_length,
+            pattern: Pattern::PubSub,
+            topic: Some("weather".to_string()),
+        };
+        let sink_cfg = cfg.clone();
+        let thread_guard = std::thread::spawn(move || {
+            let mut sink = Sink::from_config(sink_cfg).expect("Sink failed to bind");
+            let msg: String = sink.recv().expect("Sink failed to receive message");
+            assert_eq!(msg, "sunny");
+        });
+        let mut source = Source::from_config(cfg)?;
+        // Give the SUB socket time to bind and register its subscription
+        // before the PUB socket starts broadcasting (ZMQ's "slow joiner").
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        source.send("sunny")?;
+        thread_guard.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn message_too_large_is_rejected() -> Result<()> {
+        let cfg = Config {
+            host: "127.0.0.1".to_string(),
+            port: 11004, // test-specific port
+            format: Format::default(),
+            secret_key: None,
+            max_content_length: 512,
+            pattern: Pattern::default(),
+            topic: None,
+        };
+        let sink_cfg = cfg.clone();
+        let thread_guard = std::thread::spawn(move || {
+            let mut sink = Sink::from_config(sink_cfg).expect("Sink failed to bind");
+            let msg: String = sink.recv().expect("Sink failed to receive message");
+            assert_eq!(msg, "fits");
+        });
+        let mut source = Source::from_config(cfg)?;
+        let oversized = "x".repeat(1024);
+        let err = source.send(&oversized).unwrap_err();
+        assert!(matches!(err, Error::MessageTooLarge { .. }));
+        source.send("fits")?;
+        thread_guard.join().unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn encryption_seal_open_round_trip() -> Result<()> {
+        let key = SecretKey::from_bytes(&[7u8; 32])?;
+        let plaintext = b"some secret payload";
+        let sealed = encryption::seal(&key, plaintext)?;
+        assert_ne!(sealed, plaintext);
+        let opened = encryption::open(&key, &sealed)?;
+        assert_eq!(opened, plaintext);
+        Ok(())
+    }
+
+    #[test]
+    fn encryption_agreement_mismatch_is_rejected() -> Result<()> {
+        let mut cfg = Config::default();
+        cfg.secret_key = Some(SecretKey::from_bytes(&[1u8; 32])?);
+        let peer = Handshake::local(&Config::default(), Vec::new()); // no "encryption" capability
+        let err = check_encryption_agreement(&cfg, &peer).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::EncryptionMismatch { local_enabled: true, remote_enabled: false }
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn handshake_version_mismatch_is_rejected() {
+        let cfg = Config::default();
+        let local = Handshake::local(&cfg, Vec::new());
+        let mut remote = local.clone();
+        remote.protocol.0 += 1;
+        let err = local.check_compatible(&remote).unwrap_err();
+        assert!(matches!(err, Error::VersionMismatch { .. }));
+    }
+
+    #[test]
+    fn config_override_from_env_and_apply() {
+        std::env::set_var("IPC_CHAN_HOST", "192.0.2.1");
+        std::env::set_var("IPC_CHAN_PORT", "9999");
+        let overrides = ConfigOverride::from_env();
+        std::env::remove_var("IPC_CHAN_HOST");
+        std::env::remove_var("IPC_CHAN_PORT");
+        assert_eq!(overrides.host, Some("192.0.2.1".to_string()));
+        assert_eq!(overrides.port, Some(9999));
+
+        let mut cfg = Config::default();
+        cfg.apply_overrides(overrides);
+        assert_eq!(cfg.host, "192.0.2.1");
+        assert_eq!(cfg.port, 9999);
+    }
+
+    #[test]
+    fn wire_format_round_trip_msgpack_and_cbor() -> Result<()> {
+        let value = Foo("Hello World! 3".to_string(), 7);
+
+        let msgpack_bytes = Format::MessagePack.encode(&value)?;
+        let msgpack_value: Foo = Format::MessagePack.decode(&msgpack_bytes)?;
+        assert_eq!(msgpack_value, value);
+
+        let cbor_bytes = Format::Cbor.encode(&value)?;
+        let cbor_value: Foo = Format::Cbor.decode(&cbor_bytes)?;
+        assert_eq!(cbor_value, value);
+
+        Ok(())
+    }
+
 }
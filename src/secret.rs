@@ -0,0 +1,65 @@
+//! Pre-shared symmetric key used to optionally encrypt payloads.
+
+use crate::error::{Error, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Key length required by XChaCha20-Poly1305.
+pub const KEY_LEN: usize = 32;
+
+/// A pre-shared symmetric key, shared out-of-band by both peers.
+/// (De)serializes from/to a hex string in TOML, so it can be pasted into
+/// a config file; a base64 string is also accepted when parsing (see
+/// [`FromStr`]).
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SecretKey([u8; KEY_LEN]);
+
+impl SecretKey {
+    /// Build a `SecretKey` from raw bytes, which must be exactly
+    /// [`KEY_LEN`] bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != KEY_LEN {
+            return Err(Error::InvalidSecretKey);
+        }
+        let mut key = [0u8; KEY_LEN];
+        key.copy_from_slice(bytes);
+        Ok(Self(key))
+    }
+
+    #[inline(always)]
+    pub(crate) fn as_bytes(&self) -> &[u8; KEY_LEN] { &self.0 }
+}
+
+impl FromStr for SecretKey {
+    type Err = Error;
+
+    /// Parse a `SecretKey` from a hex or base64-encoded string.
+    fn from_str(encoded: &str) -> Result<Self> {
+        let bytes = hex::decode(encoded)
+            .or_else(|_| BASE64.decode(encoded))
+            .map_err(|_| Error::InvalidSecretKey)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey(..)") // NOTE don't leak the key into logs
+    }
+}
+
+impl Serialize for SecretKey {
+    fn serialize<S: Serializer>(&self, s: S) -> std::result::Result<S::Ok, S::Error> {
+        s.serialize_str(&hex::encode(self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretKey {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> std::result::Result<Self, D::Error> {
+        let encoded = String::deserialize(d)?;
+        encoded.parse::<SecretKey>().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,54 @@
+//! Wire codec module: pluggable (de)serialization of values into the
+//! bytes that actually travel over the ZMQ socket.
+
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use serde_derive::{Deserialize as DeriveDeserialize, Serialize as DeriveSerialize};
+
+/// A binary encoding that can turn any `Serialize` value into bytes
+/// and back. Swapping the `Format` on a `Config` swaps this for free.
+pub trait WireFormat {
+    /// Encode `value` into bytes suitable for transmission.
+    fn encode<V: ?Sized + Serialize>(&self, value: &V) -> Result<Vec<u8>>;
+    /// Decode a value of type `V` out of `bytes`.
+    fn decode<V: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<V>;
+}
+
+/// The wire format used to (de)serialize messages.
+/// Selected via [`Config::format`](crate::Config::format).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(DeriveDeserialize, DeriveSerialize)]
+pub enum Format {
+    /// Human-readable, and the default for backwards compatibility.
+    Json,
+    /// Compact binary format, cheaper to encode/decode than JSON.
+    MessagePack,
+    /// Compact binary format with a self-describing data model.
+    Cbor,
+}
+
+impl Default for Format {
+    fn default() -> Self { Format::Json }
+}
+
+impl WireFormat for Format {
+    fn encode<V: ?Sized + Serialize>(&self, value: &V) -> Result<Vec<u8>> {
+        match self {
+            Format::Json => Ok(serde_json::to_vec(value)?),
+            Format::MessagePack => Ok(rmp_serde::to_vec(value)?),
+            Format::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::ser::into_writer(value, &mut bytes)?;
+                Ok(bytes)
+            },
+        }
+    }
+
+    fn decode<V: for<'de> Deserialize<'de>>(&self, bytes: &[u8]) -> Result<V> {
+        match self {
+            Format::Json => Ok(serde_json::from_slice(bytes)?),
+            Format::MessagePack => Ok(rmp_serde::from_slice(bytes)?),
+            Format::Cbor => Ok(ciborium::de::from_reader(bytes)?),
+        }
+    }
+}